@@ -1,50 +1,240 @@
 #![allow(dead_code)]
 
-/// # Ring buffer
-///
-/// Implements a ring buffer. It's not very efficient (uses a standard lib vector as the backend)
-/// This only allocates once. At this time you cannot extend the length of the buffer.
-///
-/// ```
-/// # use ring::Ring;
-///
-/// fn round_trip() {
-///     let len = 16; // Must be a multiple of 2.
-///     let mut ring: Ring<usize> = Ring::with_size(len).unwrap();
-///
-///     for i in 0..len {
-///         // Returns None if the push is successful
-///         assert_eq!(None, ring.push(i));
-///     }
-///
-///     // Returns the item you tried to push if the buffer is full
-///     assert_eq!(Some(3), ring.push(3));
-///
-///     // Empty the buffer.
-///     for i in 0..len {
-///         assert_eq!(Some(i), ring.read());
-///     }
-///     assert_eq!(None, ring.read());
-///     assert_eq!(None, ring.push(len));
-///     assert_eq!(Some(len), ring.read());
-/// }
-/// ```
-
-#[derive(Debug)]
+//! # Ring buffer
+//!
+//! Implements a ring buffer backed by a raw, uninitialized allocation so it
+//! works with move-only types and doesn't pay for an `Option` tag per slot.
+//! It allocates once up front, but [`Ring::reserve`]/[`Ring::grow`] can
+//! extend it later if it turns out to be too small.
+//!
+//! ```
+//! # use ring::Ring;
+//!
+//! fn round_trip() {
+//!     let len = 16;
+//!     let mut ring: Ring<usize> = Ring::with_size(len).unwrap();
+//!
+//!     for i in 0..len {
+//!         // Returns None if the push is successful
+//!         assert_eq!(None, ring.push(i));
+//!     }
+//!
+//!     // Returns the item you tried to push if the buffer is full
+//!     assert_eq!(Some(3), ring.push(3));
+//!
+//!     // Empty the buffer.
+//!     for i in 0..len {
+//!         assert_eq!(Some(i), ring.read());
+//!     }
+//!     assert_eq!(None, ring.read());
+//!     assert_eq!(None, ring.push(len));
+//!     assert_eq!(Some(len), ring.read());
+//! }
+//! ```
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
 pub struct Ring<T> {
-    buffer: Vec<Option<T>>,
+    buffer: Box<[MaybeUninit<T>]>,
     write: usize,
     read: usize,
 }
 
-impl<T: Clone + std::fmt::Debug> Ring<T> {
+impl<T> std::fmt::Debug for Ring<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ring")
+            .field("capacity", &self.buffer.len())
+            .field("write", &self.write)
+            .field("read", &self.read)
+            .finish()
+    }
+}
+
+/// The backing store shared between a [`Producer`] and a [`Consumer`]
+/// produced by [`Ring::split`]. The producer is the sole writer of `write`
+/// and the consumer is the sole writer of `read`; each side only ever reads
+/// the other's cursor, so no lock is needed for the single-producer/
+/// single-consumer case.
+struct Shared<T> {
+    buffer: UnsafeCell<Box<[MaybeUninit<T>]>>,
+    write: AtomicUsize,
+    read: AtomicUsize,
+    capacity: usize,
+}
+
+// SAFETY: `Producer` only ever touches the slots it publishes through
+// `write`, and `Consumer` only ever touches the slots it has observed
+// through an acquire load of `write`, so the two never alias a slot at the
+// same time.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let buffer_len = self.capacity;
+        if buffer_len == 0 {
+            return;
+        }
+
+        let read = *self.read.get_mut();
+        let write = *self.write.get_mut();
+        let buffer = self.buffer.get_mut();
+
+        let mut cursor = read;
+        while cursor != write {
+            let idx = cursor % buffer_len;
+            // SAFETY: every slot in [read, write) was published by the
+            // producer and never consumed, and `&mut self` here means
+            // both the `Producer` and `Consumer` have already been
+            // dropped, so nothing else can be touching these slots.
+            unsafe { buffer[idx].assume_init_drop() };
+            cursor = cursor.wrapping_add(1);
+        }
+    }
+}
+
+/// The producing half of a split [`Ring`]. Only this half writes to the
+/// buffer and advances the write cursor.
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The consuming half of a split [`Ring`]. Only this half reads from the
+/// buffer and advances the read cursor.
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+unsafe impl<T: Send> Send for Producer<T> {}
+unsafe impl<T: Send> Send for Consumer<T> {}
+
+impl<T> Producer<T> {
+    /// Pushes `item` into the buffer, returning it back if the buffer is
+    /// currently full. The data is written before the write cursor is
+    /// published with a release store, so a consumer that observes the new
+    /// cursor value is guaranteed to see the data too.
+    pub fn push(&mut self, item: T) -> Option<T> {
+        let write = self.shared.write.load(Ordering::Relaxed);
+        let read = self.shared.read.load(Ordering::Acquire);
+        if write.wrapping_sub(read) >= self.shared.capacity {
+            return Some(item);
+        }
+
+        let idx = write % self.shared.capacity;
+        // SAFETY: only the producer writes to slots at or past `write`, and
+        // the consumer won't read this slot until it observes the release
+        // store below. We reach the slot through a pointer into just that
+        // one element rather than a `&mut` over the whole backing array, so
+        // this doesn't alias the consumer's concurrent access to other
+        // slots in the same allocation.
+        unsafe {
+            let base = (*self.shared.buffer.get()).as_ptr();
+            *(base.add(idx) as *mut MaybeUninit<T>) = MaybeUninit::new(item);
+        }
+        self.shared.write.store(write.wrapping_add(1), Ordering::Release);
+        None
+    }
+
+    pub fn is_full(&self) -> bool {
+        let write = self.shared.write.load(Ordering::Relaxed);
+        let read = self.shared.read.load(Ordering::Acquire);
+        write.wrapping_sub(read) >= self.shared.capacity
+    }
+}
+
+impl<T> Consumer<T> {
+    /// Reads the oldest element out of the buffer, or `None` if it's empty.
+    /// The write cursor is loaded with acquire ordering so that if it shows
+    /// a slot as published, the element the producer wrote there is
+    /// visible too.
+    pub fn read(&mut self) -> Option<T> {
+        let read = self.shared.read.load(Ordering::Relaxed);
+        let write = self.shared.write.load(Ordering::Acquire);
+        if read == write {
+            return None;
+        }
+
+        let idx = read % self.shared.capacity;
+        // SAFETY: this slot was published by the producer's release store to
+        // `write`, which we just observed above, and only the consumer ever
+        // takes from slots before `write`. We reach the slot through a
+        // pointer into just that one element rather than a `&mut` over the
+        // whole backing array, so this doesn't alias the producer's
+        // concurrent access to other slots in the same allocation.
+        let item = unsafe {
+            let base = (*self.shared.buffer.get()).as_ptr();
+            (*base.add(idx)).assume_init_read()
+        };
+        self.shared.read.store(read.wrapping_add(1), Ordering::Release);
+        Some(item)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let read = self.shared.read.load(Ordering::Relaxed);
+        let write = self.shared.write.load(Ordering::Acquire);
+        read == write
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        let buffer_len = self.buffer.len();
+        if buffer_len == 0 {
+            return;
+        }
+
+        let mut cursor = self.read;
+        while cursor != self.write {
+            let idx = cursor % buffer_len;
+            // SAFETY: every slot in [read, write) holds an element that was
+            // initialized by push/enqueue_many and hasn't been read out yet.
+            unsafe { self.buffer[idx].assume_init_drop() };
+            cursor = cursor.wrapping_add(1);
+        }
+    }
+}
+
+impl<T: Clone> Clone for Ring<T> {
+    fn clone(&self) -> Self {
+        let buffer_len = self.buffer.len();
+        let len = self.len();
+        let mut buffer = (0..buffer_len)
+            .map(|_| MaybeUninit::uninit())
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        for i in 0..len {
+            let idx = self.read.wrapping_add(i) % buffer_len;
+            // SAFETY: every slot in [read, write) holds an initialized
+            // element, and `i` stays within that range.
+            let item = unsafe { (*self.buffer[idx].as_ptr()).clone() };
+            buffer[i] = MaybeUninit::new(item);
+        }
+
+        Self {
+            buffer,
+            write: len,
+            read: 0,
+        }
+    }
+}
+
+impl<T> Ring<T> {
     pub fn with_size(len: usize) -> Option<Self> {
-        if len % 4 == 0 {
+        if len == 0 {
             return None;
         }
 
+        let buffer = (0..len)
+            .map(|_| MaybeUninit::uninit())
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
         Some(Self {
-            buffer: vec![None; len],
+            buffer,
             write: 0,
             read: 0,
         })
@@ -61,10 +251,7 @@ impl<T: Clone + std::fmt::Debug> Ring<T> {
     pub fn push(&mut self, item: T) -> Option<T> {
         let buffer_len = self.buffer.len();
         if self.len() < buffer_len {
-            if self.buffer[self.write % buffer_len].is_some() {
-                self.write = self.write.wrapping_add(1);
-            }
-            self.buffer[self.write % buffer_len] = Some(item);
+            self.buffer[self.write % buffer_len] = MaybeUninit::new(item);
             self.write = self.write.wrapping_add(1);
             return None;
         }
@@ -77,29 +264,405 @@ impl<T: Clone + std::fmt::Debug> Ring<T> {
         }
 
         let buffer_len = self.buffer.len();
-        let res = self.buffer[self.read % buffer_len].take();
-        if res.is_some() {
-            self.read = self.read.wrapping_add(1);
-        }
-        res
+        let idx = self.read % buffer_len;
+        // SAFETY: every slot in [read, write) was initialized by push or
+        // enqueue_many, and this index is within that range.
+        let item = unsafe { self.buffer[idx].assume_init_read() };
+        self.read = self.read.wrapping_add(1);
+        Some(item)
     }
 
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns a contiguous slice of up to `size` already-enqueued elements,
+    /// starting `offset` elements past the read cursor, without consuming
+    /// them. The slice never spans the point where the backing store wraps
+    /// around, so it may come back shorter than `size` (or empty) if the
+    /// allocated region crosses that boundary or doesn't hold `size`
+    /// elements past `offset`.
+    pub fn peek(&self, offset: usize, size: usize) -> &[T] {
+        let buffer_len = self.buffer.len();
+        let available = self.len().saturating_sub(offset);
+        let start = self.read.wrapping_add(offset) % buffer_len;
+        let len = size.min(available).min(buffer_len - start);
+
+        // SAFETY: every slot in [read, write) was initialized by push or
+        // enqueue_many, and `start..start + len` stays within that range
+        // and never crosses the end of the backing store.
+        unsafe { std::slice::from_raw_parts(self.buffer[start].as_ptr(), len) }
+    }
+
+    /// Mutable counterpart to [`Ring::peek`].
+    pub fn peek_mut(&mut self, offset: usize, size: usize) -> &mut [T] {
+        let buffer_len = self.buffer.len();
+        let available = self.len().saturating_sub(offset);
+        let start = self.read.wrapping_add(offset) % buffer_len;
+        let len = size.min(available).min(buffer_len - start);
+
+        // SAFETY: see `Ring::peek`.
+        unsafe { std::slice::from_raw_parts_mut(self.buffer[start].as_mut_ptr(), len) }
+    }
+
+    /// Returns a mutable view of up to `size` elements of free space,
+    /// starting `offset` elements past the write cursor, for callers that
+    /// want to fill it in place before committing it with
+    /// [`Ring::enqueue_unallocated`]. Like `peek`, the slice never spans the
+    /// wrap point and so may come back shorter than `size`.
+    ///
+    /// The elements are not yet initialized, hence `&mut [MaybeUninit<T>]`
+    /// rather than `&mut [T]`: forming a `&mut [T]` over uninitialized
+    /// storage would already violate `T`'s validity invariant before any
+    /// write happens. Initialize each slot with [`MaybeUninit::write`] (or
+    /// similar) before committing it with [`Ring::enqueue_unallocated`], and
+    /// never read from a slot you haven't written.
+    pub fn get_unallocated(&mut self, offset: usize, size: usize) -> &mut [MaybeUninit<T>] {
+        let buffer_len = self.buffer.len();
+        let free = buffer_len - self.len();
+        let available = free.saturating_sub(offset);
+        let start = self.write.wrapping_add(offset) % buffer_len;
+        let len = size.min(available).min(buffer_len - start);
+
+        &mut self.buffer[start..start + len]
+    }
+
+    /// Splits the buffer into a [`Producer`]/[`Consumer`] pair sharing one
+    /// backing allocation, so one thread can push while another reads
+    /// without a mutex.
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        // `Ring` has a `Drop` impl, so its fields can't be moved out of it
+        // directly; wrap it in `ManuallyDrop` so we can take ownership of
+        // the buffer once and skip the (now redundant) element drops.
+        let this = std::mem::ManuallyDrop::new(self);
+        let capacity = this.buffer.len();
+        // SAFETY: `this` never runs its `Drop` impl, and we read `buffer`
+        // out of it exactly once here.
+        let buffer = unsafe { std::ptr::read(&this.buffer) };
+
+        let shared = Arc::new(Shared {
+            buffer: UnsafeCell::new(buffer),
+            write: AtomicUsize::new(this.write),
+            read: AtomicUsize::new(this.read),
+            capacity,
+        });
+
+        (
+            Producer {
+                shared: shared.clone(),
+            },
+            Consumer { shared },
+        )
+    }
+
+    /// Copies as many elements as are available out of the buffer into
+    /// `out` and returns the number copied. Works in at most two contiguous
+    /// spans, mirroring `enqueue_many`.
+    #[must_use]
+    pub fn dequeue_many(&mut self, out: &mut [T]) -> usize {
+        let buffer_len = self.buffer.len();
+        let to_read = out.len().min(self.len());
+
+        let mut read = 0;
+        while read < to_read {
+            let start = self.read % buffer_len;
+            let span = (to_read - read).min(buffer_len - start);
+            for offset in 0..span {
+                // SAFETY: every slot in [read, write) was initialized by
+                // push or enqueue_many, and this index is within that range.
+                out[read + offset] = unsafe { self.buffer[start + offset].assume_init_read() };
+            }
+            self.read = self.read.wrapping_add(span);
+            read += span;
+        }
+        read
+    }
+
+    /// Alias of [`Ring::dequeue_many`] for callers that think in terms of
+    /// slices rather than bulk queues.
+    #[must_use]
+    pub fn dequeue_slice(&mut self, out: &mut [T]) -> usize {
+        self.dequeue_many(out)
+    }
+
+    /// Advances the write cursor by `count`, committing elements previously
+    /// written via [`Ring::write_unallocated`] or [`Ring::get_unallocated`].
+    ///
+    /// Every one of the `count` slots being committed must already hold a
+    /// genuinely initialized `T` — not merely fit within the unallocated
+    /// region. `count <= unallocated` is a necessary bound, checked
+    /// unconditionally (including in release builds) and enforced by a
+    /// panic, but it is not sufficient on its own. Committing a slot that
+    /// was never written is undefined behavior: `Ring`'s `Drop` impl (and
+    /// `read`/`dequeue_many`) will later call
+    /// `assume_init_drop`/`assume_init_read` on every slot in
+    /// `[read, write)` without distinguishing "committed" from
+    /// "initialized".
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` exceeds the unallocated region.
+    pub fn enqueue_unallocated(&mut self, count: usize) {
+        let unallocated = self.buffer.len() - self.len();
+        assert!(
+            count <= unallocated,
+            "enqueue_unallocated: tried to commit {} elements but only {} are unallocated",
+            count,
+            unallocated
+        );
+        self.write = self.write.wrapping_add(count);
+    }
+
+    /// Pushes `item` into the buffer. If the buffer is full, the oldest
+    /// element is evicted and returned to make room instead of rejecting
+    /// the new item, giving callers a fixed-size overwriting mode
+    /// alongside the bounded-growing one.
+    pub fn push_overwrite(&mut self, item: T) -> Option<T> {
+        let buffer_len = self.buffer.len();
+        let evicted = if self.len() == buffer_len {
+            self.read()
+        } else {
+            None
+        };
+
+        self.buffer[self.write % buffer_len] = MaybeUninit::new(item);
+        self.write = self.write.wrapping_add(1);
+        evicted
+    }
+
+    /// Grows the buffer to `new_cap` elements, re-linearizing the existing
+    /// elements so their logical order `[read, write)` is preserved
+    /// starting at index 0. Does nothing if `new_cap` isn't larger than the
+    /// current capacity.
+    pub fn grow(&mut self, new_cap: usize) {
+        let old_cap = self.buffer.len();
+        if new_cap <= old_cap {
+            return;
+        }
+
+        let len = self.len();
+        let mut new_buffer = (0..new_cap)
+            .map(|_| MaybeUninit::uninit())
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        for (i, slot) in new_buffer.iter_mut().enumerate().take(len) {
+            let idx = self.read.wrapping_add(i) % old_cap;
+            // SAFETY: every slot in [read, write) holds an initialized
+            // element that hasn't been read out yet, and `i` stays within
+            // that range.
+            let item = unsafe { self.buffer[idx].assume_init_read() };
+            *slot = MaybeUninit::new(item);
+        }
+
+        self.buffer = new_buffer;
+        self.read = 0;
+        self.write = len;
+    }
+
+    /// Grows the buffer's capacity by `additional` elements.
+    pub fn reserve(&mut self, additional: usize) {
+        self.grow(self.buffer.len() + additional);
+    }
+}
+
+impl<T: Clone> Ring<T> {
+    /// Copies as many `items` as fit into the buffer and returns the number
+    /// copied. Works in at most two contiguous spans (split at the point
+    /// where `write` wraps around the end of the backing store) instead of
+    /// pushing one element at a time.
+    #[must_use]
+    pub fn enqueue_many(&mut self, items: &[T]) -> usize {
+        let buffer_len = self.buffer.len();
+        let available = buffer_len - self.len();
+        let to_write = items.len().min(available);
+
+        let mut written = 0;
+        while written < to_write {
+            let start = self.write % buffer_len;
+            let span = (to_write - written).min(buffer_len - start);
+            for offset in 0..span {
+                self.buffer[start + offset] = MaybeUninit::new(items[written + offset].clone());
+            }
+            self.write = self.write.wrapping_add(span);
+            written += span;
+        }
+        written
+    }
+
+    /// Alias of [`Ring::enqueue_many`] for callers that think in terms of
+    /// slices rather than bulk queues.
+    #[must_use]
+    pub fn enqueue_slice(&mut self, items: &[T]) -> usize {
+        self.enqueue_many(items)
+    }
+
+    /// Copies `data` into the unallocated region starting `offset` elements
+    /// past the write cursor, without advancing the write cursor. Returns
+    /// the number of elements actually copied, which may be less than
+    /// `data.len()` if it doesn't fit before the wrap point or the buffer
+    /// doesn't have that much free space. Pair with
+    /// [`Ring::enqueue_unallocated`] to commit the write once it's known to
+    /// be contiguous.
+    #[must_use]
+    pub fn write_unallocated(&mut self, offset: usize, data: &[T]) -> usize {
+        let dest = self.get_unallocated(offset, data.len());
+        let len = dest.len();
+        for (slot, item) in dest[..len].iter_mut().zip(&data[..len]) {
+            slot.write(item.clone());
+        }
+        len
+    }
+}
+
+/// Returned by [`PacketBuffer::enqueue`] when there isn't room for the
+/// packet, either because the payload ring is full or because the metadata
+/// ring has run out of slots to track it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Full;
+
+impl std::fmt::Display for Full {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "packet buffer is full")
+    }
+}
+
+impl std::error::Error for Full {}
+
+/// A single metadata-ring entry: either a real packet's header and payload
+/// length, or a padding marker recording how many payload slots were
+/// skipped to keep the next packet contiguous.
+#[derive(Debug, Clone)]
+enum Entry<H> {
+    Packet { header: H, len: usize },
+    Padding { len: usize },
+}
+
+/// A queue of discrete, variably-sized packets layered on two [`Ring`]s: a
+/// metadata ring tracking each packet's header and length, and a payload
+/// ring holding the raw elements. Each packet's payload is kept contiguous
+/// by inserting a padding metadata entry whenever it would otherwise wrap
+/// past the end of the payload buffer.
+///
+/// The payload type is bounded by `Copy` rather than `Clone`: a padding
+/// entry commits payload-ring slots that are never actually written (see
+/// `enqueue`), and `dequeue` hands back a packet's payload without moving it
+/// out of the ring. Two things make this sound:
+///
+/// - A `Copy` type can never have drop glue (the two are mutually
+///   exclusive), so `Ring`'s `Drop` impl has nothing to (incorrectly) call
+///   on a never-written padding slot even if it's still within
+///   `[read, write)` when the buffer is dropped.
+/// - Padding slots are never read back as `T` in the first place: `dequeue`
+///   skips a padding span with a bare cursor bump, never materializing a
+///   reference to it, and only ever returns the slice belonging to a real
+///   `Entry::Packet` span, which `enqueue` guarantees is fully written
+///   before it's recorded.
+///
+/// The second point matters independently of the first: `Copy` alone
+/// doesn't rule out types with validity invariants (`bool`, `char`,
+/// `NonZero*`, niche-optimized enums), so this buffer relies on never
+/// producing a reference into uninitialized memory, not merely on skipping
+/// drop glue for it.
+pub struct PacketBuffer<H, T> {
+    metadata: Ring<Entry<H>>,
+    payload: Ring<T>,
+}
+
+impl<H, T: Copy> PacketBuffer<H, T> {
+    pub fn with_capacity(max_packets: usize, payload_capacity: usize) -> Option<Self> {
+        Some(Self {
+            metadata: Ring::with_size(max_packets)?,
+            payload: Ring::with_size(payload_capacity)?,
+        })
+    }
+
+    /// Reserves a contiguous span of the payload ring and copies `payload`
+    /// into it, recording `header` alongside it in the metadata ring. If the
+    /// payload would otherwise wrap past the end of the payload buffer, a
+    /// padding metadata entry is inserted first so the packet itself never
+    /// spans the wrap point.
+    pub fn enqueue(&mut self, header: H, payload: &[T]) -> Result<(), Full> {
+        let payload_cap = self.payload.buffer.len();
+        let free = payload_cap - self.payload.len();
+
+        let write_offset = self.payload.write % payload_cap;
+        let contiguous = payload_cap - write_offset;
+        let needs_padding = payload.len() > contiguous;
+
+        // A padding entry wastes `contiguous` elements of free space in
+        // addition to the packet itself, since the payload ring's write
+        // cursor jumps straight past them without ever filling them in.
+        let payload_needed = if needs_padding {
+            payload.len() + contiguous
+        } else {
+            payload.len()
+        };
+        if payload_needed > free {
+            return Err(Full);
+        }
+
+        let metadata_needed = if needs_padding { 2 } else { 1 };
+        let metadata_cap = self.metadata.buffer.len();
+        if self.metadata.len() + metadata_needed > metadata_cap {
+            return Err(Full);
+        }
+
+        if needs_padding {
+            self.metadata.push(Entry::Padding { len: contiguous });
+            self.payload.enqueue_unallocated(contiguous);
+        }
+
+        self.metadata.push(Entry::Packet {
+            header,
+            len: payload.len(),
+        });
+        let written = self.payload.enqueue_many(payload);
+        debug_assert_eq!(written, payload.len());
+
+        Ok(())
+    }
+
+    /// Returns the oldest packet's header and a contiguous slice of its
+    /// payload, skipping over any padding entries along the way. The
+    /// payload is only borrowed, not moved out of the ring; a padding span
+    /// is skipped with a raw cursor bump and never turned into a reference
+    /// at all. See the type-level doc comment on `PacketBuffer` for why
+    /// this is sound.
+    pub fn dequeue(&mut self) -> Option<(H, &[T])> {
+        loop {
+            match self.metadata.read()? {
+                Entry::Padding { len } => {
+                    self.payload.read = self.payload.read.wrapping_add(len);
+                }
+                Entry::Packet { header, len } => {
+                    let payload_cap = self.payload.buffer.len();
+                    let start = self.payload.read % payload_cap;
+                    // SAFETY: `enqueue` only ever records a packet's length
+                    // in the metadata ring after writing that many
+                    // initialized, contiguous elements starting here, and
+                    // this is the first time they're read back out.
+                    let payload = unsafe {
+                        std::slice::from_raw_parts(self.payload.buffer[start].as_ptr(), len)
+                    };
+                    self.payload.read = self.payload.read.wrapping_add(len);
+                    return Some((header, payload));
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Ring;
+    use super::{Full, PacketBuffer, Ring};
+    use std::thread;
 
     #[test]
     fn wrap_at_end() {
-        let mut ring: Ring<usize> = Ring {
-            buffer: vec![None; 4],
-            write: std::usize::MAX,
-            read: std::usize::MAX,
-        };
+        let mut ring: Ring<usize> = Ring::with_size(4).unwrap();
+        ring.write = std::usize::MAX;
+        ring.read = std::usize::MAX;
         assert_eq!(0, ring.len());
         ring.push(std::usize::MAX);
         println!("{:?}", ring);
@@ -115,4 +678,281 @@ mod test {
         assert_eq!(Some(1), ring.read());
     }
 
+    #[test]
+    fn enqueue_dequeue_many_wraps() {
+        let mut ring: Ring<usize> = Ring::with_size(5).unwrap();
+        ring.push(0);
+        ring.push(1);
+        assert_eq!(Some(0), ring.read());
+        assert_eq!(Some(1), ring.read());
+
+        // write is now at index 2 of 5, so this 5-element enqueue wraps.
+        assert_eq!(5, ring.enqueue_many(&[10, 11, 12, 13, 14]));
+
+        let mut out = [0usize; 5];
+        assert_eq!(5, ring.dequeue_many(&mut out));
+        assert_eq!([10, 11, 12, 13, 14], out);
+    }
+
+    #[test]
+    fn enqueue_many_stops_when_full() {
+        let mut ring: Ring<usize> = Ring::with_size(5).unwrap();
+        assert_eq!(5, ring.enqueue_many(&[1, 2, 3, 4, 5, 6, 7]));
+        assert_eq!(5, ring.len());
+    }
+
+    #[test]
+    fn with_size_accepts_any_nonzero_length() {
+        assert!(Ring::<usize>::with_size(4).is_some());
+        assert!(Ring::<usize>::with_size(8).is_some());
+        assert!(Ring::<usize>::with_size(0).is_none());
+    }
+
+    #[test]
+    fn clone_preserves_logical_order() {
+        let mut ring: Ring<usize> = Ring::with_size(5).unwrap();
+        ring.push(1);
+        ring.push(2);
+        ring.push(3);
+        ring.read();
+        ring.push(4);
+
+        let mut cloned = ring.clone();
+        assert_eq!(Some(2), cloned.read());
+        assert_eq!(Some(3), cloned.read());
+        assert_eq!(Some(4), cloned.read());
+        assert_eq!(None, cloned.read());
+
+        // The original is untouched by cloning.
+        assert_eq!(3, ring.len());
+    }
+
+    #[test]
+    fn drop_runs_for_move_only_elements() {
+        use std::rc::Rc;
+
+        let marker = Rc::new(());
+        let mut ring: Ring<Rc<()>> = Ring::with_size(4).unwrap();
+        ring.push(marker.clone());
+        ring.push(marker.clone());
+        ring.read();
+
+        assert_eq!(2, Rc::strong_count(&marker));
+        drop(ring);
+        assert_eq!(1, Rc::strong_count(&marker));
+    }
+
+    #[test]
+    fn peek_does_not_consume_and_stops_at_wrap() {
+        let mut ring: Ring<usize> = Ring::with_size(5).unwrap();
+        assert_eq!(5, ring.enqueue_many(&[1, 2, 3, 4, 5]));
+        ring.read();
+        ring.read();
+        assert_eq!(2, ring.enqueue_many(&[6, 7]));
+
+        // The allocated region is [3, 4, 5, 6, 7]. Asking for 4 elements
+        // starting at offset 1 would cover [4, 5, 6, 7], but the backing
+        // store wraps right after 5, so only the first span comes back.
+        assert_eq!(&[4, 5], ring.peek(1, 4));
+        assert_eq!(5, ring.len());
+
+        assert_eq!(Some(3), ring.read());
+        assert_eq!(Some(4), ring.read());
+    }
+
+    #[test]
+    fn get_unallocated_exposes_free_region() {
+        let mut ring: Ring<usize> = Ring::with_size(5).unwrap();
+        ring.push(1);
+
+        let free = ring.get_unallocated(0, 4);
+        assert_eq!(4, free.len());
+        for (i, slot) in free.iter_mut().enumerate() {
+            slot.write(i);
+        }
+
+        // Nothing has been committed yet, so the buffer still only reports
+        // the one element that was pushed.
+        assert_eq!(1, ring.len());
+    }
+
+    #[test]
+    fn write_unallocated_then_commit() {
+        let mut ring: Ring<usize> = Ring::with_size(5).unwrap();
+        ring.push(1);
+
+        assert_eq!(3, ring.write_unallocated(0, &[2, 3, 4]));
+        ring.enqueue_unallocated(3);
+
+        assert_eq!(4, ring.len());
+        assert_eq!(Some(1), ring.read());
+        assert_eq!(Some(2), ring.read());
+        assert_eq!(Some(3), ring.read());
+        assert_eq!(Some(4), ring.read());
+    }
+
+    #[test]
+    fn write_unallocated_initializes_move_only_elements() {
+        // Regression test: `get_unallocated` used to hand back `&mut [T]`
+        // over uninitialized storage, and `write_unallocated` wrote into it
+        // with `clone_from_slice`, which drops the (garbage) old value
+        // before writing the new one. For a heap-allocating `T` like
+        // `String` that's an instant segfault; this only passes because
+        // both now go through `MaybeUninit` properly.
+        let mut ring: Ring<String> = Ring::with_size(4).unwrap();
+        assert_eq!(1, ring.write_unallocated(0, &["a".to_string()]));
+        ring.enqueue_unallocated(1);
+
+        assert_eq!(Some("a".to_string()), ring.read());
+    }
+
+    #[test]
+    #[should_panic]
+    fn enqueue_unallocated_rejects_overcommit() {
+        let mut ring: Ring<usize> = Ring::with_size(5).unwrap();
+        ring.enqueue_unallocated(6);
+    }
+
+    #[test]
+    fn split_round_trips_across_threads() {
+        let ring: Ring<usize> = Ring::with_size(5).unwrap();
+        let (mut producer, mut consumer) = ring.split();
+
+        let producer_thread = thread::spawn(move || {
+            for i in 0..1000 {
+                while producer.push(i).is_some() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let consumer_thread = thread::spawn(move || {
+            let mut seen = Vec::with_capacity(1000);
+            while seen.len() < 1000 {
+                if let Some(item) = consumer.read() {
+                    seen.push(item);
+                }
+            }
+            seen
+        });
+
+        producer_thread.join().unwrap();
+        let seen = consumer_thread.join().unwrap();
+        assert_eq!((0..1000).collect::<Vec<_>>(), seen);
+    }
+
+    #[test]
+    fn split_drop_runs_for_unread_elements() {
+        use std::rc::Rc;
+
+        let marker = Rc::new(());
+        let ring: Ring<Rc<()>> = Ring::with_size(4).unwrap();
+        let (mut producer, consumer) = ring.split();
+        producer.push(marker.clone());
+        producer.push(marker.clone());
+
+        assert_eq!(3, Rc::strong_count(&marker));
+        drop(producer);
+        drop(consumer);
+        assert_eq!(1, Rc::strong_count(&marker));
+    }
+
+    #[test]
+    fn packet_buffer_round_trips() {
+        let mut buffer: PacketBuffer<u8, u8> = PacketBuffer::with_capacity(4, 8).unwrap();
+        buffer.enqueue(1, &[1, 2, 3]).unwrap();
+        buffer.enqueue(2, &[4, 5]).unwrap();
+
+        assert_eq!(Some((1, &[1u8, 2, 3][..])), buffer.dequeue());
+        assert_eq!(Some((2, &[4u8, 5][..])), buffer.dequeue());
+        assert_eq!(None, buffer.dequeue());
+    }
+
+    #[test]
+    fn packet_buffer_pads_around_the_wrap() {
+        let mut buffer: PacketBuffer<u8, u8> = PacketBuffer::with_capacity(4, 8).unwrap();
+        buffer.enqueue(1, &[1, 2, 3, 4, 5]).unwrap();
+        assert_eq!(Some((1, &[1u8, 2, 3, 4, 5][..])), buffer.dequeue());
+
+        // The payload ring's write cursor is now at offset 5 of 8, so this
+        // packet doesn't fit contiguously and forces a padding entry.
+        buffer.enqueue(2, &[10, 20, 30, 40]).unwrap();
+        assert_eq!(Some((2, &[10u8, 20, 30, 40][..])), buffer.dequeue());
+    }
+
+    #[test]
+    fn packet_buffer_rejects_when_payload_is_full() {
+        let mut buffer: PacketBuffer<u8, u8> = PacketBuffer::with_capacity(4, 4).unwrap();
+        buffer.enqueue(1, &[1, 2, 3]).unwrap();
+        assert_eq!(Err(Full), buffer.enqueue(2, &[4, 5]));
+    }
+
+    #[test]
+    fn packet_buffer_rejects_when_padding_would_exceed_free_space() {
+        let mut buffer: PacketBuffer<u8, u8> = PacketBuffer::with_capacity(8, 8).unwrap();
+        buffer.enqueue(1, &[1, 2]).unwrap();
+        buffer.enqueue(2, &[3, 4, 5, 6]).unwrap();
+        assert_eq!(Some((1, &[1u8, 2][..])), buffer.dequeue());
+
+        // The payload ring now has 4 elements free, but only 2 of them are
+        // contiguous before the wrap. A length-3 packet needs a 2-element
+        // padding entry on top of its own 3 elements - 5 total, more than
+        // the 4 free - so this must be rejected outright rather than
+        // silently writing a packet shorter than the length its metadata
+        // entry claims.
+        assert_eq!(Err(Full), buffer.enqueue(3, &[7, 8, 9]));
+    }
+
+    #[test]
+    fn packet_buffer_rejects_when_metadata_is_full() {
+        let mut buffer: PacketBuffer<u8, u8> = PacketBuffer::with_capacity(1, 16).unwrap();
+        buffer.enqueue(1, &[1]).unwrap();
+        assert_eq!(Err(Full), buffer.enqueue(2, &[2]));
+    }
+
+    #[test]
+    fn push_overwrite_evicts_oldest_when_full() {
+        let mut ring: Ring<usize> = Ring::with_size(3).unwrap();
+        assert_eq!(None, ring.push_overwrite(1));
+        assert_eq!(None, ring.push_overwrite(2));
+        assert_eq!(None, ring.push_overwrite(3));
+        assert_eq!(Some(1), ring.push_overwrite(4));
+
+        assert_eq!(Some(2), ring.read());
+        assert_eq!(Some(3), ring.read());
+        assert_eq!(Some(4), ring.read());
+        assert_eq!(None, ring.read());
+    }
+
+    #[test]
+    fn grow_preserves_order_across_the_wrap() {
+        let mut ring: Ring<usize> = Ring::with_size(4).unwrap();
+        ring.push(1);
+        ring.push(2);
+        ring.push(3);
+        ring.read();
+        ring.read();
+        ring.push(4);
+        ring.push(5);
+        // Logical order is now [3, 4, 5], physically wrapped in a 4-slot
+        // buffer.
+
+        ring.grow(8);
+        assert_eq!(3, ring.len());
+        assert_eq!(Some(3), ring.read());
+        assert_eq!(Some(4), ring.read());
+        assert_eq!(Some(5), ring.read());
+        assert_eq!(None, ring.read());
+
+        // The grown capacity sticks around.
+        assert_eq!(0, ring.enqueue_many(&[0; 0]));
+        assert_eq!(8, ring.enqueue_many(&[10, 20, 30, 40, 50, 60, 70, 80]));
+    }
+
+    #[test]
+    fn reserve_grows_by_the_requested_amount() {
+        let mut ring: Ring<usize> = Ring::with_size(4).unwrap();
+        ring.reserve(4);
+        assert_eq!(8, ring.enqueue_many(&[1, 2, 3, 4, 5, 6, 7, 8]));
+    }
 }